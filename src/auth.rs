@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, Utc};
+use oauth2::basic::BasicClient;
+use oauth2::reqwest::async_http_client;
+use oauth2::url::Url;
+use oauth2::{AuthType, AuthorizationCode, CsrfToken, PkceCodeChallenge, RedirectUrl, Scope, TokenResponse};
+
+use crate::cache::CacheFile;
+use crate::crypto::CacheKey;
+use crate::{Config, CACHE_KEY_ENV_VAR};
+
+/// Port the local redirect listener binds to while waiting for the OAuth callback.
+const REDIRECT_PORT: u16 = 8723;
+
+/// Runs the OAuth2 authorization-code + PKCE flow and writes the resulting
+/// tokens into both the config file and the cache file.
+pub async fn login(config_path: &str) -> Result<()> {
+	let mut config: Config = serde_json::from_reader(File::open(Path::new(config_path))?)?;
+
+	let client = BasicClient::new(
+		config.client_id.clone(),
+		Some(config.client_secret.clone()),
+		config.authorization_url.clone(),
+		Some(config.access_token_url.clone())
+	)
+		.set_auth_type(AuthType::RequestBody)
+		.set_redirect_uri(RedirectUrl::new(format!("http://localhost:{}/callback", REDIRECT_PORT))?);
+
+	let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+	let (auth_url, csrf_token) = client.authorize_url(CsrfToken::new_random)
+		.add_scope(Scope::new("api".to_string()))
+		.add_scope(Scope::new("offline_access".to_string()))
+		.set_pkce_challenge(pkce_challenge)
+		.url();
+
+	println!("Open this URL in a browser to authorize panoptocord:\n\n{}\n", auth_url);
+	println!("Waiting for the redirect on http://localhost:{}/callback ...", REDIRECT_PORT);
+
+	let (code, state) = receive_redirect(REDIRECT_PORT)?;
+	if state.secret() != csrf_token.secret() {
+		bail!("CSRF state returned by the redirect didn't match, aborting login");
+	}
+
+	let token = client.exchange_code(code)
+		.set_pkce_verifier(pkce_verifier)
+		.request_async(async_http_client).await
+		.context("Failed to exchange authorization code for tokens")?;
+
+	config.refresh_token = token.refresh_token()
+		.context("Server did not return a refresh token; was the offline_access scope granted?")?
+		.clone();
+	config.access_token = token.access_token().clone();
+	serde_json::to_writer_pretty(File::create(Path::new(config_path))?, &config)?;
+
+	let mut cache = CacheFile::fresh(config.refresh_token.clone(), config.access_token.clone());
+	if let Some(expires_in) = token.expires_in() {
+		cache.access_token_expires = (Utc::now() + Duration::from_std(expires_in)?) - Duration::minutes(5);
+	}
+	let cache_key = CacheKey::from_env(CACHE_KEY_ENV_VAR)?;
+	crate::cache::save(cache_key.as_ref(), &cache)?;
+
+	println!("Login complete! Tokens written to {} and the cache file.", config_path);
+	Ok(())
+}
+
+/// Blocks on a single localhost connection carrying the OAuth redirect's `code` and `state`.
+fn receive_redirect(port: u16) -> Result<(AuthorizationCode, CsrfToken)> {
+	let listener = TcpListener::bind(("127.0.0.1", port)).context("Failed to bind local redirect listener")?;
+	let (mut stream, _) = listener.accept().context("Failed to accept redirect connection")?;
+
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+	let path = request_line.split_whitespace().nth(1).context("Malformed redirect request")?;
+	let url = Url::parse(&format!("http://localhost{}", path))?;
+
+	let mut code = None;
+	let mut state = None;
+	for (key, value) in url.query_pairs() {
+		match key.as_ref() {
+			"code" => code = Some(value.into_owned()),
+			"state" => state = Some(value.into_owned()),
+			_ => {}
+		}
+	}
+
+	let body = "<html><body>panoptocord login complete, you can close this tab.</body></html>";
+	write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nContent-Type: text/html\r\n\r\n{}", body.len(), body)?;
+
+	Ok((
+		AuthorizationCode::new(code.context("Redirect did not include an authorization code")?),
+		CsrfToken::new(state.context("Redirect did not include a state parameter")?)
+	))
+}
@@ -0,0 +1,31 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(4);
+
+/// Retries `f` with exponential backoff (1s, 2s, 4s, capped), up to `MAX_ATTEMPTS`
+/// attempts in total, returning the first success or the final error.
+pub async fn retry<F, Fut, T>(mut f: F) -> Result<T>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T>>,
+{
+	let mut delay = INITIAL_DELAY;
+	let mut attempt = 1;
+	loop {
+		match f().await {
+			Ok(value) => return Ok(value),
+			Err(err) if attempt < MAX_ATTEMPTS => {
+				eprintln!("Attempt {}/{} failed, retrying in {:?}: {:?}", attempt, MAX_ATTEMPTS, delay, err);
+				tokio::time::sleep(delay).await;
+				delay = std::cmp::min(delay * 2, MAX_DELAY);
+				attempt += 1;
+			}
+			Err(err) => return Err(err),
+		}
+	}
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use oauth2::{AccessToken, RefreshToken};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::CacheKey;
+
+const CACHE_PATH: &str = "panoptocord-cache.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheFile {
+	#[serde(default)]
+	pub cached_recordings: Vec<String>,
+	pub refresh_token: RefreshToken,
+	pub access_token: AccessToken,
+	pub access_token_expires: DateTime<Utc>,
+	pub color_map: HashMap<String, u32>,
+	// Caches the color derived from a session's thumbnail, keyed by thumbnail URL,
+	// so it's only downloaded and decoded once
+	#[serde(default)]
+	pub thumbnail_colors: HashMap<String, u32>,
+	pub last_changed_refresh_token: RefreshToken,
+	pub last_changed_access_token: AccessToken,
+}
+
+impl CacheFile {
+	/// Builds a fresh cache with an already-expired access token, so the
+	/// first loop iteration refreshes it.
+	pub fn fresh(refresh_token: RefreshToken, access_token: AccessToken) -> CacheFile {
+		CacheFile {
+			cached_recordings: Vec::new(),
+			last_changed_refresh_token: refresh_token.clone(),
+			last_changed_access_token: access_token.clone(),
+			refresh_token,
+			access_token,
+			access_token_expires: Utc.ymd(2020, 1, 1).and_hms(0, 0, 0),
+			color_map: HashMap::new(),
+			thumbnail_colors: HashMap::new()
+		}
+	}
+}
+
+/// The on-disk shape of `CacheFile`, with token fields as plain `String`s so
+/// they can hold an encrypted blob instead of the plaintext token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheFileOnDisk {
+	#[serde(default)]
+	pub cached_recordings: Vec<String>,
+	pub refresh_token: String,
+	pub access_token: String,
+	pub access_token_expires: DateTime<Utc>,
+	pub color_map: HashMap<String, u32>,
+	#[serde(default)]
+	pub thumbnail_colors: HashMap<String, u32>,
+	pub last_changed_refresh_token: String,
+	pub last_changed_access_token: String,
+}
+
+/// Loads the cache file from disk, decrypting its token fields if `key` is supplied.
+pub fn load(key: Option<&CacheKey>) -> Result<CacheFile> {
+	let on_disk: CacheFileOnDisk = serde_json::from_reader(File::open(Path::new(CACHE_PATH))?)?;
+
+	let decode = |value: &str| -> Result<String> {
+		match key {
+			Some(key) => Ok(key.decrypt(value)?.expose_secret().clone()),
+			None => Ok(value.to_string())
+		}
+	};
+
+	Ok(CacheFile {
+		cached_recordings: on_disk.cached_recordings,
+		refresh_token: RefreshToken::new(decode(&on_disk.refresh_token)?),
+		access_token: AccessToken::new(decode(&on_disk.access_token)?),
+		access_token_expires: on_disk.access_token_expires,
+		color_map: on_disk.color_map,
+		thumbnail_colors: on_disk.thumbnail_colors,
+		last_changed_refresh_token: RefreshToken::new(decode(&on_disk.last_changed_refresh_token)?),
+		last_changed_access_token: AccessToken::new(decode(&on_disk.last_changed_access_token)?)
+	})
+}
+
+/// Saves `cache` to disk, encrypting its token fields if `key` is supplied.
+pub fn save(key: Option<&CacheKey>, cache: &CacheFile) -> Result<()> {
+	let encode = |value: &str| -> Result<String> {
+		match key {
+			Some(key) => key.encrypt(value),
+			None => Ok(value.to_string())
+		}
+	};
+
+	let on_disk = CacheFileOnDisk {
+		cached_recordings: cache.cached_recordings.clone(),
+		refresh_token: encode(cache.refresh_token.secret())?,
+		access_token: encode(cache.access_token.secret())?,
+		access_token_expires: cache.access_token_expires,
+		color_map: cache.color_map.clone(),
+		thumbnail_colors: cache.thumbnail_colors.clone(),
+		last_changed_refresh_token: encode(cache.last_changed_refresh_token.secret())?,
+		last_changed_access_token: encode(cache.last_changed_access_token.secret())?
+	};
+	serde_json::to_writer_pretty(File::create(Path::new(CACHE_PATH))?, &on_disk)?;
+	Ok(())
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+/// Watches `config_path` for modifications and sends a notification on the
+/// returned channel each time the file is written to, from a dedicated
+/// blocking thread. Watches the parent directory rather than the file itself,
+/// since an atomic-replace write would unlink the file's inode and silently
+/// stop a direct watch from ever firing again.
+pub fn spawn(config_path: String) -> Result<mpsc::UnboundedReceiver<()>> {
+	let (tx, rx) = mpsc::unbounded_channel();
+	let (raw_tx, raw_rx) = std::sync::mpsc::channel();
+
+	let config_path = Path::new(&config_path).canonicalize().context("Failed to resolve config file path")?;
+	let file_name = config_path.file_name().context("Config path has no file name")?.to_owned();
+	let dir = match config_path.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+		_ => bail!("Config path has no parent directory to watch"),
+	};
+
+	let mut watcher: RecommendedWatcher = notify::recommended_watcher(raw_tx)
+		.context("Failed to create config file watcher")?;
+	watcher.watch(&dir, RecursiveMode::NonRecursive)
+		.context("Failed to watch config file's parent directory")?;
+
+	std::thread::spawn(move || {
+		// Keep the watcher alive for as long as this thread runs
+		let _watcher = watcher;
+		for res in raw_rx {
+			match res {
+				Ok(event) if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+					&& event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str())) =>
+				{
+					if tx.send(()).is_err() {
+						break;
+					}
+				}
+				Ok(_) => {}
+				Err(err) => eprintln!("Config watcher error: {:?}", err),
+			}
+		}
+	});
+
+	Ok(rx)
+}
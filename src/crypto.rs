@@ -0,0 +1,55 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use rand::RngCore;
+use secrecy::{ExposeSecret, Secret};
+
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit key used to encrypt/decrypt cache tokens at rest.
+pub struct CacheKey(Secret<[u8; 32]>);
+
+impl CacheKey {
+	/// Loads the cache encryption key from `env_var`, base64-decoded. `None` if unset.
+	pub fn from_env(env_var: &str) -> Result<Option<CacheKey>> {
+		match std::env::var(env_var) {
+			Ok(encoded) => {
+				let bytes = STANDARD.decode(encoded.trim()).context("Cache key is not valid base64")?;
+				let key: [u8; 32] = bytes.try_into().map_err(|_| anyhow!("Cache key must be 32 bytes (AES-256) once base64-decoded"))?;
+				Ok(Some(CacheKey(Secret::new(key))))
+			}
+			Err(std::env::VarError::NotPresent) => Ok(None),
+			Err(err) => Err(err).context("Failed to read cache key env var"),
+		}
+	}
+
+	/// Encrypts `plaintext` into a base64 blob of a random nonce plus ciphertext.
+	pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+		let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.0.expose_secret()));
+		let mut nonce_bytes = [0u8; NONCE_LEN];
+		rand::thread_rng().fill_bytes(&mut nonce_bytes);
+		let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_bytes())
+			.map_err(|_| anyhow!("Failed to encrypt cache token"))?;
+
+		let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+		out.extend_from_slice(&nonce_bytes);
+		out.extend_from_slice(&ciphertext);
+		Ok(STANDARD.encode(out))
+	}
+
+	/// Decrypts a blob produced by [`CacheKey::encrypt`].
+	pub fn decrypt(&self, encoded: &str) -> Result<Secret<String>> {
+		let data = STANDARD.decode(encoded).context("Encrypted cache token is not valid base64")?;
+		if data.len() < NONCE_LEN {
+			bail!("Encrypted cache token is too short to contain a nonce");
+		}
+		let (nonce_bytes, ciphertext) = data.split_at(NONCE_LEN);
+
+		let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.0.expose_secret()));
+		let plaintext = cipher.decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+			.map_err(|_| anyhow!("Failed to decrypt cache token, wrong key?"))?;
+		Ok(Secret::new(String::from_utf8(plaintext).context("Decrypted cache token is not valid UTF-8")?))
+	}
+}
@@ -0,0 +1,304 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::PanoptoSession;
+
+/// A parsed `filter` expression from `Config`, evaluated against each
+/// `PanoptoSession` to decide whether it should be posted.
+pub struct Filter {
+	expr: Expr,
+}
+
+impl Filter {
+	pub fn parse(input: &str) -> Result<Filter> {
+		let tokens = tokenize(input)?;
+		let mut parser = Parser { tokens: &tokens, pos: 0 };
+		let expr = parser.parse_or()?;
+		if parser.pos != tokens.len() {
+			bail!("Unexpected trailing tokens after position {} in filter expression", parser.pos);
+		}
+		Ok(Filter { expr })
+	}
+
+	/// Evaluates the filter against `session`. A leaf whose field is missing
+	/// (e.g. no `description`) evaluates to false rather than erroring.
+	pub fn evaluate(&self, session: &PanoptoSession) -> bool {
+		eval(&self.expr, session)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+	And(Box<Expr>, Box<Expr>),
+	Or(Box<Expr>, Box<Expr>),
+	Leaf { field: Vec<String>, op: Op, value: Value },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+	Eq,
+	Ne,
+	Gt,
+	Lt,
+	Ge,
+	Le,
+	Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	String(String),
+	Number(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+	Ident(String),
+	String(String),
+	Number(f64),
+	Op(Op),
+	And,
+	Or,
+	LParen,
+	RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+	let chars: Vec<char> = input.chars().collect();
+	let mut tokens = Vec::new();
+	let mut i = 0;
+
+	while i < chars.len() {
+		let c = chars[i];
+		if c.is_whitespace() {
+			i += 1;
+			continue;
+		}
+
+		match c {
+			'(' => {
+				tokens.push(Token::LParen);
+				i += 1;
+			}
+			')' => {
+				tokens.push(Token::RParen);
+				i += 1;
+			}
+			'"' => {
+				i += 1;
+				let start = i;
+				while i < chars.len() && chars[i] != '"' {
+					i += 1;
+				}
+				if i >= chars.len() {
+					bail!("Unterminated string literal in filter expression");
+				}
+				tokens.push(Token::String(chars[start..i].iter().collect()));
+				i += 1;
+			}
+			'=' => {
+				tokens.push(Token::Op(Op::Eq));
+				i += 1;
+			}
+			'!' if chars.get(i + 1) == Some(&'=') => {
+				tokens.push(Token::Op(Op::Ne));
+				i += 2;
+			}
+			'>' if chars.get(i + 1) == Some(&'=') => {
+				tokens.push(Token::Op(Op::Ge));
+				i += 2;
+			}
+			'>' => {
+				tokens.push(Token::Op(Op::Gt));
+				i += 1;
+			}
+			'<' if chars.get(i + 1) == Some(&'=') => {
+				tokens.push(Token::Op(Op::Le));
+				i += 2;
+			}
+			'<' => {
+				tokens.push(Token::Op(Op::Lt));
+				i += 1;
+			}
+			c if c.is_ascii_digit() => {
+				let start = i;
+				while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+					i += 1;
+				}
+				let text: String = chars[start..i].iter().collect();
+				tokens.push(Token::Number(text.parse().with_context(|| format!("Invalid number literal '{}'", text))?));
+			}
+			c if c.is_alphabetic() || c == '_' => {
+				let start = i;
+				while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+					i += 1;
+				}
+				let text: String = chars[start..i].iter().collect();
+				tokens.push(match text.as_str() {
+					"AND" => Token::And,
+					"OR" => Token::Or,
+					"CONTAINS" => Token::Op(Op::Contains),
+					_ => Token::Ident(text),
+				});
+			}
+			other => bail!("Unexpected character '{}' in filter expression", other),
+		}
+	}
+
+	Ok(tokens)
+}
+
+struct Parser<'a> {
+	tokens: &'a [Token],
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.pos)
+	}
+
+	fn advance(&mut self) -> Option<&Token> {
+		let token = self.tokens.get(self.pos);
+		self.pos += 1;
+		token
+	}
+
+	// `AND` binds tighter than `OR`
+	fn parse_or(&mut self) -> Result<Expr> {
+		let mut left = self.parse_and()?;
+		while matches!(self.peek(), Some(Token::Or)) {
+			self.pos += 1;
+			let right = self.parse_and()?;
+			left = Expr::Or(Box::new(left), Box::new(right));
+		}
+		Ok(left)
+	}
+
+	fn parse_and(&mut self) -> Result<Expr> {
+		let mut left = self.parse_term()?;
+		while matches!(self.peek(), Some(Token::And)) {
+			self.pos += 1;
+			let right = self.parse_term()?;
+			left = Expr::And(Box::new(left), Box::new(right));
+		}
+		Ok(left)
+	}
+
+	fn parse_term(&mut self) -> Result<Expr> {
+		if matches!(self.peek(), Some(Token::LParen)) {
+			self.pos += 1;
+			let inner = self.parse_or()?;
+			match self.advance() {
+				Some(Token::RParen) => return Ok(inner),
+				other => bail!("Expected closing parenthesis, found {:?}", other),
+			}
+		}
+		self.parse_leaf()
+	}
+
+	fn parse_leaf(&mut self) -> Result<Expr> {
+		let field = match self.advance() {
+			Some(Token::Ident(name)) => name.split('.').map(str::to_string).collect(),
+			other => bail!("Expected a field name, found {:?}", other),
+		};
+		let op = match self.advance() {
+			Some(Token::Op(op)) => *op,
+			other => bail!("Expected a comparison operator, found {:?}", other),
+		};
+		let value = match self.advance() {
+			Some(Token::String(s)) => Value::String(s.clone()),
+			Some(Token::Number(n)) => Value::Number(*n),
+			other => bail!("Expected a value, found {:?}", other),
+		};
+		Ok(Expr::Leaf { field, op, value })
+	}
+}
+
+enum FieldValue<'a> {
+	Str(&'a str),
+	Number(f64),
+	Date(DateTime<Utc>),
+	Missing,
+}
+
+fn resolve_field<'a>(session: &'a PanoptoSession, path: &[String]) -> FieldValue<'a> {
+	match (path.first().map(String::as_str), path.get(1).map(String::as_str)) {
+		(Some("name"), _) => FieldValue::Str(&session.name),
+		(Some("description"), _) => session.description.as_deref().map(FieldValue::Str).unwrap_or(FieldValue::Missing),
+		(Some("duration"), _) => FieldValue::Number(session.duration),
+		(Some("startTime"), _) => session.start_time.map(FieldValue::Date).unwrap_or(FieldValue::Missing),
+		(Some("folderDetails"), Some("name")) => FieldValue::Str(&session.folder_details.name),
+		(Some("folderDetails"), Some("id")) => FieldValue::Str(&session.folder_details.id),
+		(Some("createdBy"), Some("username")) => session.created_by.username.as_deref().map(FieldValue::Str).unwrap_or(FieldValue::Missing),
+		(Some("createdBy"), Some("id")) => FieldValue::Str(&session.created_by.id),
+		_ => FieldValue::Missing,
+	}
+}
+
+fn eval(expr: &Expr, session: &PanoptoSession) -> bool {
+	match expr {
+		Expr::And(a, b) => eval(a, session) && eval(b, session),
+		Expr::Or(a, b) => eval(a, session) || eval(b, session),
+		Expr::Leaf { field, op, value } => match resolve_field(session, field) {
+			FieldValue::Missing => false,
+			FieldValue::Str(actual) => eval_string(actual, *op, value),
+			FieldValue::Number(actual) => eval_number(actual, *op, value),
+			FieldValue::Date(actual) => eval_date(actual, *op, value),
+		},
+	}
+}
+
+fn eval_string(actual: &str, op: Op, value: &Value) -> bool {
+	let expected = match value {
+		Value::String(s) => s.as_str(),
+		Value::Number(_) => return false,
+	};
+	match op {
+		Op::Eq => actual == expected,
+		Op::Ne => actual != expected,
+		Op::Contains => actual.contains(expected),
+		Op::Gt => actual > expected,
+		Op::Lt => actual < expected,
+		Op::Ge => actual >= expected,
+		Op::Le => actual <= expected,
+	}
+}
+
+fn eval_number(actual: f64, op: Op, value: &Value) -> bool {
+	let expected = match value {
+		Value::Number(n) => *n,
+		Value::String(s) => match s.parse::<f64>() {
+			Ok(n) => n,
+			Err(_) => return false,
+		},
+	};
+	match op {
+		Op::Eq => actual == expected,
+		Op::Ne => actual != expected,
+		Op::Gt => actual > expected,
+		Op::Lt => actual < expected,
+		Op::Ge => actual >= expected,
+		Op::Le => actual <= expected,
+		Op::Contains => false,
+	}
+}
+
+fn eval_date(actual: DateTime<Utc>, op: Op, value: &Value) -> bool {
+	let expected = match value {
+		Value::String(s) => match DateTime::parse_from_rfc3339(s) {
+			Ok(dt) => dt.with_timezone(&Utc),
+			Err(_) => return false,
+		},
+		Value::Number(_) => return false,
+	};
+	match op {
+		Op::Eq => actual == expected,
+		Op::Ne => actual != expected,
+		Op::Gt => actual > expected,
+		Op::Lt => actual < expected,
+		Op::Ge => actual >= expected,
+		Op::Le => actual <= expected,
+		Op::Contains => false,
+	}
+}
@@ -1,53 +1,68 @@
-use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
 
 use anyhow::{Context, format_err, Result};
-use chrono::{DateTime, Duration, TimeZone, Utc};
-use futures::future::try_join_all;
+use chrono::{DateTime, Duration, Utc};
 use oauth2::{AuthType, Scope, TokenResponse};
 use oauth2::basic::BasicClient;
 use oauth2::reqwest::async_http_client;
 use random_color::RandomColor;
 use serde::{Deserialize, Serialize};
 
-mod webhook;
+mod auth;
+mod backoff;
+mod cache;
+mod config_watch;
+mod crypto;
+mod filter;
+mod metrics;
+mod sinks;
+mod thumbnail_color;
+
+use cache::CacheFile;
+use crypto::CacheKey;
+use sinks::{RecordingInfo, RecordingSink, SinkConfig};
+
+/// Env var holding a base64-encoded AES-256 key used to encrypt OAuth tokens
+/// at rest in the cache file. When unset, the cache stays plaintext.
+pub(crate) const CACHE_KEY_ENV_VAR: &str = "PANOPTOCORD_CACHE_KEY";
 
 #[tokio::main]
 async fn main() -> Result<()> {
-	let config_path = std::env::args().nth(1).unwrap_or("config.json".to_string());
+	let mut args = std::env::args().skip(1);
+	let first_arg = args.next();
+
+	if first_arg.as_deref() == Some("login") {
+		let config_path = args.next().unwrap_or("config.json".to_string());
+		return auth::login(&config_path).await;
+	}
+
+	let config_path = first_arg.unwrap_or("config.json".to_string());
 	println!("Loading configuration from file: {}", config_path);
 
-	fn read_cache() -> Result<CacheFile> {
-		let reader = File::open(Path::new("panoptocord-cache.json"))?;
+	fn read_config(path: &str) -> Result<Config> {
+		let reader = File::open(Path::new(path))?;
 		Ok(serde_json::from_reader(reader)?)
 	}
 
-	let config: Config = serde_json::from_reader(File::open(Path::new(&config_path))?)?;
-	let mut cache: CacheFile = read_cache()
+	let mut config: Config = read_config(&config_path)?;
+	let mut config_changes = config_watch::spawn(config_path.clone())?;
+	let mut sinks: Vec<Box<dyn RecordingSink>> = config.sinks.iter().map(SinkConfig::build).collect();
+	let cache_key = CacheKey::from_env(CACHE_KEY_ENV_VAR)?;
+
+	if let Some(metrics_port) = config.metrics_port {
+		metrics::install(metrics_port)?;
+	}
+
+	let mut cache: CacheFile = cache::load(cache_key.as_ref())
 		.or_else(|_err| -> Result<CacheFile> {
-			let new_file = CacheFile {
-				cached_recordings: Vec::new(),
-				refresh_token: config.refresh_token.clone(),
-				access_token: config.access_token.clone(),
-				access_token_expires: Utc.ymd(2020, 1, 1).and_hms(0, 0, 0),
-				color_map: HashMap::new(),
-				last_changed_refresh_token: config.refresh_token.clone(),
-				last_changed_access_token: config.access_token.clone()
-			};
-			Ok(new_file)
+			Ok(CacheFile::fresh(config.refresh_token.clone(), config.access_token.clone()))
 		})?;
 
 	// If the config was updated after the cache was last updated, refresh access tokens
-	if cache.last_changed_refresh_token.secret() != config.refresh_token.secret() || cache.last_changed_access_token.secret() != config.access_token.secret() {
-		println!("Token invalidated, refreshing...");
-		cache.last_changed_access_token = config.access_token.clone();
-		cache.last_changed_refresh_token = config.refresh_token.clone();
-		cache.access_token = config.access_token.clone();
-		cache.refresh_token = config.refresh_token.clone();
-		refresh_token(&mut cache, &config).await?;
-		let _ = serde_json::to_writer_pretty(File::create(Path::new("panoptocord-cache.json"))?, &cache)?;
-		println!("Token refreshed!");
+	if invalidate_tokens_if_changed(&mut cache, &config).await? {
+		metrics::gauge!(metrics::ACCESS_TOKEN_EXPIRES, (cache.access_token_expires - Utc::now()).num_seconds() as f64);
+		let _ = cache::save(cache_key.as_ref(), &cache)?;
 	}
 
 	println!("Starting request loop...");
@@ -56,42 +71,52 @@ async fn main() -> Result<()> {
 	let client = reqwest::Client::new();
 	loop {
 		interval.tick().await;
+
+		// Drain any config file changes; only swap in configs that parse cleanly,
+		// so a partial write of config.json just gets ignored until it's re-saved.
+		while config_changes.try_recv().is_ok() {
+			match read_config(&config_path) {
+				Ok(new_config) => {
+					println!("Config file changed, reloading...");
+					config = new_config;
+					sinks = config.sinks.iter().map(SinkConfig::build).collect();
+					match invalidate_tokens_if_changed(&mut cache, &config).await {
+						Ok(_) => {
+							let _ = cache::save(cache_key.as_ref(), &cache)?;
+						}
+						Err(err) => eprintln!("Error refreshing access token after config reload: {:?}", err),
+					}
+				}
+				Err(err) => eprintln!("Failed to reload config, keeping previous config: {:?}", err),
+			}
+		}
+
 		if cache.access_token_expires.lt(&Utc::now()) {
 			println!("Token expired, refreshing...");
 			if let Err(err) = refresh_token(&mut cache, &config).await {
 				eprintln!("Error refreshing access token: {:?}", err);
-				let _ = webhook::post_message(config.webhook_error_url.clone(), "Failed to refresh access token!".to_string()).await;
+				metrics::counter!(metrics::TOKEN_REFRESH_FAILED, 1);
+				report_error(&sinks, "Failed to refresh access token!").await;
 			} else {
+				metrics::counter!(metrics::TOKEN_REFRESH_SUCCEEDED, 1);
 				// Save the file
-				let _ = serde_json::to_writer_pretty(File::create(Path::new("panoptocord-cache.json"))?, &cache)?;
+				let _ = cache::save(cache_key.as_ref(), &cache)?;
 				println!("Token refreshed!");
 			}
 		}
+		metrics::gauge!(metrics::ACCESS_TOKEN_EXPIRES, (cache.access_token_expires - Utc::now()).num_seconds() as f64);
 
-		if let Err(err) = make_requests(&mut cache, &config, &client).await {
+		if let Err(err) = make_requests(&mut cache, &config, &client, &sinks).await {
 			eprintln!("Error making requests: {:?}", err);
 		} else {
-			let _ = serde_json::to_writer_pretty(File::create(Path::new("panoptocord-cache.json"))?, &cache)?;
+			let _ = cache::save(cache_key.as_ref(), &cache)?;
 		}
 	}
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct CacheFile {
-	#[serde(default)]
-	pub cached_recordings: Vec<String>,
-	pub refresh_token: oauth2::RefreshToken,
-	pub access_token: oauth2::AccessToken,
-	pub access_token_expires: DateTime<Utc>,
-	pub color_map: HashMap<String, u32>,
-	pub last_changed_refresh_token: oauth2::RefreshToken,
-	pub last_changed_access_token: oauth2::AccessToken,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Config {
+pub(crate) struct Config {
 	pub authorization_url: oauth2::AuthUrl,
 	pub access_token_url: oauth2::TokenUrl,
 	pub client_id: oauth2::ClientId,
@@ -99,11 +124,18 @@ struct Config {
 	pub refresh_token: oauth2::RefreshToken,
 	pub access_token: oauth2::AccessToken,
 	pub folders: Vec<String>,
-	pub webhook_url: String,
-	pub webhook_error_url: String,
+	pub sinks: Vec<SinkConfig>,
 	pub panopto_base: String,
 	// Allows filtering with a start date, to stop duplicate messages with an incomplete cache
-	pub filter_since_date: Option<DateTime<Utc>>
+	pub filter_since_date: Option<DateTime<Utc>>,
+	// A small boolean query language, e.g. `duration > 600 AND name CONTAINS "Lecture"`
+	pub filter: Option<String>,
+	// When true, derive each embed's color from its session's thumbnail instead of
+	// the random per-folder color
+	#[serde(default)]
+	pub color_from_thumbnail: bool,
+	// If set, serves Prometheus metrics on this port
+	pub metrics_port: Option<u16>
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -175,7 +207,11 @@ pub struct FolderDetails {
 	pub name: String,
 }
 
-async fn make_requests(cache: &mut CacheFile, config: &Config, client: &reqwest::Client) -> Result<()> {
+/// Polls every configured folder independently so a transient failure (a single
+/// folder's Panopto fetch, or a single webhook post) can't abort the other
+/// folders' progress for this cycle; each folder retries its own requests with
+/// exponential backoff and reports its own errors without tearing down the loop.
+async fn make_requests(cache: &mut CacheFile, config: &Config, client: &reqwest::Client, sinks: &[Box<dyn RecordingSink>]) -> Result<()> {
 	for f in &config.folders {
 		if !cache.color_map.contains_key(f) {
 			let color = RandomColor::new().to_rgb_array();
@@ -183,15 +219,50 @@ async fn make_requests(cache: &mut CacheFile, config: &Config, client: &reqwest:
 		}
 	}
 
-	let responses = try_join_all(config.folders.iter()
-		.map(|folder| make_request(
-			&cache.access_token, &folder,
-			&config.panopto_base, client))).await?;
-	let mut sessions: Vec<PanoptoSession> = responses.into_iter().flat_map(|res| res.results).collect();
+	let filter = match &config.filter {
+		Some(expr) => Some(filter::Filter::parse(expr).context("Invalid filter expression, blocking this cycle so it doesn't post unfiltered")?),
+		None => None,
+	};
+
+	let outcomes = futures::future::join_all(config.folders.iter()
+		.map(|folder| process_folder(folder, cache, config, filter.as_ref(), client, sinks))).await;
+
+	for outcome in outcomes {
+		cache.cached_recordings.extend(outcome.posted);
+		cache.thumbnail_colors.extend(outcome.thumbnail_colors);
+	}
+
+	Ok(())
+}
+
+/// The result of polling a single folder: the ids of recordings that were
+/// successfully posted, and any thumbnail colors computed along the way
+/// (new cache entries, merged back in by `make_requests` once every folder finishes).
+struct FolderOutcome {
+	posted: Vec<String>,
+	thumbnail_colors: Vec<(String, u32)>,
+}
+
+/// Fetches, filters and posts new recordings for a single folder, stopping (and
+/// reporting an error to the configured sinks) as soon as a request exhausts its
+/// retries, while still returning the ids that were successfully posted so far.
+async fn process_folder(folder: &String, cache: &CacheFile, config: &Config, filter: Option<&filter::Filter>, client: &reqwest::Client, sinks: &[Box<dyn RecordingSink>]) -> FolderOutcome {
+	let mut posted = Vec::new();
+	let mut thumbnail_colors = Vec::new();
+
+	let response = match backoff::retry(|| make_request(&cache.access_token, folder, &config.panopto_base, client)).await {
+		Ok(response) => response,
+		Err(err) => {
+			metrics::counter!(metrics::PANOPTO_REQUEST_ERRORS, 1, "folder" => folder.clone());
+			report_folder_error(sinks, folder, &err).await;
+			return FolderOutcome { posted, thumbnail_colors };
+		}
+	};
+
+	let mut sessions = response.results;
 	// Sort oldest to newest
 	sessions.sort_by(|a, b| a.start_time.cmp(&b.start_time));
 
-	// Send messages in order
 	for session in sessions {
 		let sess_id = session.id.clone();
 		if let (Some(filter_since_date), Some(start_time)) = (config.filter_since_date, session.start_time) {
@@ -199,16 +270,81 @@ async fn make_requests(cache: &mut CacheFile, config: &Config, client: &reqwest:
 				continue;
 			}
 		}
-		if !cache.cached_recordings.contains(&sess_id) {
-			let color = cache.color_map.get(&session.folder_details.id).unwrap().clone();
-			send_discord_message(&config.webhook_url, &config.panopto_base, session, color).await?;
-			// Wait 2000ms to ensure correct ordering
-			tokio::time::sleep(Duration::milliseconds(2000).to_std()?).await;
-			cache.cached_recordings.push(sess_id)
+		if cache.cached_recordings.contains(&sess_id) || posted.contains(&sess_id) {
+			continue;
+		}
+		if let Some(filter) = filter {
+			if !filter.evaluate(&session) {
+				continue;
+			}
 		}
+
+		let color = if config.color_from_thumbnail {
+			match cache.thumbnail_colors.get(&session.urls.thumbnail_url) {
+				Some(color) => *color,
+				None => match thumbnail_color::compute_color(client, &session.urls.thumbnail_url).await {
+					Ok(color) => {
+						thumbnail_colors.push((session.urls.thumbnail_url.clone(), color));
+						color
+					}
+					Err(err) => {
+						eprintln!("Failed to derive color from thumbnail {}, falling back to folder color: {:?}", session.urls.thumbnail_url, err);
+						cache.color_map.get(&session.folder_details.id).copied().unwrap_or_default()
+					}
+				}
+			}
+		} else {
+			cache.color_map.get(&session.folder_details.id).copied().unwrap_or_default()
+		};
+		let recording = build_recording_info(session, color, &config.panopto_base);
+
+		if let Err(err) = post_to_sinks(&recording, sinks).await {
+			metrics::counter!(metrics::SINK_POST_FAILURES, 1, "folder" => folder.clone());
+			report_folder_error(sinks, folder, &err).await;
+			break;
+		}
+
+		metrics::counter!(metrics::RECORDINGS_POSTED_TOTAL, 1);
+		metrics::counter!(metrics::RECORDINGS_POSTED_FOLDER, 1, "folder" => folder.clone());
+		// Wait 2000ms to ensure correct ordering
+		if let Ok(sleep_duration) = Duration::milliseconds(2000).to_std() {
+			tokio::time::sleep(sleep_duration).await;
+		}
+		posted.push(sess_id);
 	}
 
-	Ok(())
+	FolderOutcome { posted, thumbnail_colors }
+}
+
+async fn report_folder_error(sinks: &[Box<dyn RecordingSink>], folder: &String, err: &anyhow::Error) {
+	let message = format!("Error processing folder {}: {:?}", folder, err);
+	eprintln!("{}", message);
+	report_error(sinks, &message).await;
+}
+
+/// Fans an operational error message out to every configured sink's
+/// `post_error`. Discord sinks post it to their webhook; the Lemmy sink only
+/// logs it to stderr and does not post it to its community.
+async fn report_error(sinks: &[Box<dyn RecordingSink>], message: &str) {
+	for sink in sinks {
+		if let Err(err) = sink.post_error(message).await {
+			eprintln!("Failed to report error to sink: {:?}", err);
+		}
+	}
+}
+
+fn build_recording_info(session: PanoptoSession, color: u32, panopto_base: &str) -> RecordingInfo {
+	RecordingInfo {
+		name: session.name,
+		description: session.description,
+		folder_name: session.folder_details.name,
+		folder_url: format!("{}Panopto/Pages/Sessions/List.aspx#folderID=%22{}%22", panopto_base, session.folder_details.id),
+		color,
+		start_time: session.start_time.unwrap_or(Utc::now()),
+		viewer_url: session.urls.viewer_url,
+		thumbnail_url: session.urls.thumbnail_url,
+		duration: chrono::Duration::seconds(session.duration as i64)
+	}
 }
 
 async fn make_request(access_token: &oauth2::AccessToken, folder_id: &String, panopto_base: &String, client: &reqwest::Client) -> Result<PanoptoResponse> {
@@ -219,6 +355,25 @@ async fn make_request(access_token: &oauth2::AccessToken, folder_id: &String, pa
 		.json::<PanoptoResponse>().await?)
 }
 
+/// Refreshes tokens from `config` into `cache` if `config`'s refresh/access tokens
+/// differ from the ones the cache was last updated against (e.g. after startup,
+/// or after a hot-reloaded config.json supplies new credentials). Returns whether
+/// a refresh was performed, so callers know whether the cache needs saving.
+async fn invalidate_tokens_if_changed(cache: &mut CacheFile, config: &Config) -> Result<bool> {
+	if cache.last_changed_refresh_token.secret() != config.refresh_token.secret() || cache.last_changed_access_token.secret() != config.access_token.secret() {
+		println!("Token invalidated, refreshing...");
+		cache.last_changed_access_token = config.access_token.clone();
+		cache.last_changed_refresh_token = config.refresh_token.clone();
+		cache.access_token = config.access_token.clone();
+		cache.refresh_token = config.refresh_token.clone();
+		refresh_token(cache, config).await?;
+		println!("Token refreshed!");
+		Ok(true)
+	} else {
+		Ok(false)
+	}
+}
+
 async fn refresh_token(cache: &mut CacheFile, config: &Config) -> Result<()> {
 	let client = BasicClient::new(
 		config.client_id.clone(),
@@ -256,17 +411,18 @@ async fn refresh_token(cache: &mut CacheFile, config: &Config) -> Result<()> {
 	}
 }
 
-async fn send_discord_message(webhook_url: &String, panopto_base: &String, session: PanoptoSession, color: u32) -> Result<()> {
-	webhook::post_recording(
-		session.name,
-		session.folder_details.name,
-		webhook_url.clone(),
-		color,
-		session.start_time.unwrap_or(Utc::now()),
-		session.urls.viewer_url,
-		session.urls.thumbnail_url,
-		format!("{}Panopto/Pages/Sessions/List.aspx#folderID=%22{}%22", panopto_base, session.folder_details.id),
-		chrono::Duration::seconds(session.duration as i64),
-		session.description
-	).await
+/// Posts `recording` to every sink concurrently, retrying each sink on its own
+/// with backoff. Retrying per-sink (instead of retrying the whole fan-out)
+/// means a transiently-failing sink doesn't cause one that already succeeded
+/// to receive a duplicate post.
+async fn post_to_sinks(recording: &RecordingInfo, sinks: &[Box<dyn RecordingSink>]) -> Result<()> {
+	let results = futures::future::join_all(sinks.iter()
+		.map(|sink| backoff::retry(|| sink.post_recording(recording)))).await;
+
+	let errors: Vec<String> = results.into_iter().filter_map(Result::err).map(|err| format!("{:?}", err)).collect();
+	if errors.is_empty() {
+		Ok(())
+	} else {
+		Err(format_err!("{} of {} sinks failed to post: {}", errors.len(), sinks.len(), errors.join("; ")))
+	}
 }
\ No newline at end of file
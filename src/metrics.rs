@@ -0,0 +1,32 @@
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use metrics_exporter_prometheus::PrometheusBuilder;
+
+pub const RECORDINGS_POSTED_TOTAL: &str = "panoptocord_recordings_posted_total";
+pub const RECORDINGS_POSTED_FOLDER: &str = "panoptocord_recordings_posted_folder_total";
+pub const TOKEN_REFRESH_SUCCEEDED: &str = "panoptocord_token_refresh_succeeded_total";
+pub const TOKEN_REFRESH_FAILED: &str = "panoptocord_token_refresh_failed_total";
+pub const PANOPTO_REQUEST_ERRORS: &str = "panoptocord_panopto_request_errors_total";
+pub const SINK_POST_FAILURES: &str = "panoptocord_sink_post_failures_total";
+pub const ACCESS_TOKEN_EXPIRES: &str = "panoptocord_access_token_expires_seconds";
+
+/// Starts the Prometheus metrics HTTP server on `port`.
+pub fn install(port: u16) -> Result<()> {
+	let addr: SocketAddr = ([0, 0, 0, 0], port).into();
+	PrometheusBuilder::new()
+		.with_http_listener(addr)
+		.install()
+		.context("Failed to install Prometheus metrics exporter")?;
+
+	metrics::describe_counter!(RECORDINGS_POSTED_TOTAL, "Total number of recordings posted to notification sinks");
+	metrics::describe_counter!(RECORDINGS_POSTED_FOLDER, "Number of recordings posted, per Panopto folder");
+	metrics::describe_counter!(TOKEN_REFRESH_SUCCEEDED, "Number of successful OAuth access token refreshes");
+	metrics::describe_counter!(TOKEN_REFRESH_FAILED, "Number of failed OAuth access token refreshes");
+	metrics::describe_counter!(PANOPTO_REQUEST_ERRORS, "Number of failed Panopto API requests");
+	metrics::describe_counter!(SINK_POST_FAILURES, "Number of failed recording deliveries to a configured sink");
+	metrics::describe_gauge!(ACCESS_TOKEN_EXPIRES, "Seconds remaining until the cached access token expires");
+
+	println!("Metrics server listening on 0.0.0.0:{}", port);
+	Ok(())
+}
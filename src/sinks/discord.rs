@@ -0,0 +1,117 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::{RecordingInfo, RecordingSink};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscordRequest {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub content: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub embeds: Option<Vec<Embed>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Embed {
+	pub title: String,
+	pub description: Option<String>,
+	pub url: String,
+	pub color: u32,
+	pub timestamp: DateTime<Utc>,
+	pub footer: Footer,
+	pub image: Image,
+	pub author: Author,
+	pub fields: Vec<Field>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Footer {
+	pub text: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Image {
+	pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Author {
+	pub name: String,
+	pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Field {
+	pub name: String,
+	pub value: String,
+}
+
+pub struct DiscordSink {
+	webhook_url: String,
+}
+
+impl DiscordSink {
+	pub fn new(webhook_url: String) -> Self {
+		Self { webhook_url }
+	}
+}
+
+#[async_trait]
+impl RecordingSink for DiscordSink {
+	async fn post_recording(&self, recording: &RecordingInfo) -> Result<()> {
+		post_webhook(&self.webhook_url, DiscordRequest {
+			embeds: Some(vec![
+				Embed {
+					title: recording.name.clone(),
+					description: recording.description.clone(),
+					url: recording.viewer_url.clone(),
+					color: recording.color,
+					timestamp: recording.start_time,
+					footer: Footer {
+						text: "panoptocord".to_string()
+					},
+					image: Image {
+						url: recording.thumbnail_url.clone()
+					},
+					author: Author {
+						name: recording.folder_name.clone(),
+						url: recording.folder_url.clone()
+					},
+					fields: vec![
+						Field {
+							name: "Duration".to_string(),
+							value: humantime::format_duration(recording.duration.to_std()?).to_string()
+						}
+					]
+				}
+			]),
+			content: None
+		}).await
+	}
+
+	async fn post_error(&self, message: &str) -> Result<()> {
+		post_message(&self.webhook_url, message).await
+	}
+}
+
+async fn post_message(webhook_url: &str, message: &str) -> Result<()> {
+	post_webhook(webhook_url, DiscordRequest {
+		content: Some(message.to_string()),
+		embeds: None
+	}).await
+}
+
+async fn post_webhook(webhook_url: &str, req: DiscordRequest) -> Result<()> {
+	let client = reqwest::Client::new();
+	let new_url = webhook_url.to_string() + "?wait=true";
+	client.post(&new_url).json(&req).send().await?;
+	Ok(())
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::{RecordingInfo, RecordingSink};
+
+/// Posts new recordings to a community on a Lemmy instance. Logs in lazily
+/// on first use and caches the resulting JWT for subsequent posts.
+pub struct LemmySink {
+	instance_url: String,
+	username: String,
+	password: String,
+	community: String,
+	jwt: Mutex<Option<String>>,
+}
+
+impl LemmySink {
+	pub fn new(instance_url: String, username: String, password: String, community: String) -> Self {
+		Self {
+			instance_url,
+			username,
+			password,
+			community,
+			jwt: Mutex::new(None)
+		}
+	}
+
+	async fn login(&self, client: &reqwest::Client) -> Result<String> {
+		#[derive(Serialize)]
+		struct LoginRequest<'a> {
+			username_or_email: &'a str,
+			password: &'a str,
+		}
+
+		#[derive(Deserialize)]
+		struct LoginResponse {
+			jwt: Option<String>,
+		}
+
+		let res: LoginResponse = client.post(&format!("{}/api/v3/user/login", self.instance_url))
+			.json(&LoginRequest { username_or_email: &self.username, password: &self.password })
+			.send().await?
+			.json().await?;
+		res.jwt.context("Lemmy login did not return a JWT")
+	}
+
+	async fn jwt(&self, client: &reqwest::Client) -> Result<String> {
+		let mut cached = self.jwt.lock().await;
+		if let Some(jwt) = cached.as_ref() {
+			return Ok(jwt.clone());
+		}
+		let jwt = self.login(client).await?;
+		*cached = Some(jwt.clone());
+		Ok(jwt)
+	}
+
+	async fn resolve_community_id(&self, client: &reqwest::Client, jwt: &str) -> Result<i32> {
+		#[derive(Deserialize)]
+		struct CommunityResponse {
+			community_view: CommunityView,
+		}
+
+		#[derive(Deserialize)]
+		struct CommunityView {
+			community: CommunityId,
+		}
+
+		#[derive(Deserialize)]
+		struct CommunityId {
+			id: i32,
+		}
+
+		let res: CommunityResponse = client.get(&format!("{}/api/v3/community", self.instance_url))
+			.query(&[("name", self.community.as_str()), ("auth", jwt)])
+			.send().await?
+			.json().await?;
+		Ok(res.community_view.community.id)
+	}
+}
+
+#[async_trait]
+impl RecordingSink for LemmySink {
+	async fn post_recording(&self, recording: &RecordingInfo) -> Result<()> {
+		let client = reqwest::Client::new();
+		let jwt = self.jwt(&client).await?;
+		let community_id = self.resolve_community_id(&client, &jwt).await?;
+
+		#[derive(Serialize)]
+		struct CreatePostRequest<'a> {
+			name: &'a str,
+			community_id: i32,
+			url: Option<&'a str>,
+			body: Option<String>,
+			auth: &'a str,
+		}
+
+		client.post(&format!("{}/api/v3/post", self.instance_url))
+			.json(&CreatePostRequest {
+				name: &recording.name,
+				community_id,
+				url: Some(recording.viewer_url.as_str()),
+				body: Some(format!("![]({})\n\n[{}]({})", recording.thumbnail_url, recording.folder_name, recording.viewer_url)),
+				auth: &jwt
+			})
+			.send().await?;
+		Ok(())
+	}
+
+	async fn post_error(&self, message: &str) -> Result<()> {
+		eprintln!("Lemmy sink does not post errors to a community, dropping: {}", message);
+		Ok(())
+	}
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+mod discord;
+mod lemmy;
+
+pub use discord::DiscordSink;
+pub use lemmy::LemmySink;
+
+/// Everything a `RecordingSink` needs to announce a newly-posted Panopto
+/// recording. Built once per session in `process_folder` and shared across
+/// every configured sink.
+#[derive(Debug, Clone)]
+pub struct RecordingInfo {
+	pub name: String,
+	pub description: Option<String>,
+	pub folder_name: String,
+	pub folder_url: String,
+	pub color: u32,
+	pub start_time: DateTime<Utc>,
+	pub viewer_url: String,
+	pub thumbnail_url: String,
+	pub duration: Duration,
+}
+
+/// A destination that new recordings (and operational errors) get posted to.
+#[async_trait]
+pub trait RecordingSink: Send + Sync {
+	async fn post_recording(&self, recording: &RecordingInfo) -> Result<()>;
+	async fn post_error(&self, message: &str) -> Result<()>;
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+	Discord {
+		webhook_url: String,
+	},
+	Lemmy {
+		instance_url: String,
+		username: String,
+		password: String,
+		community: String,
+	},
+}
+
+// Manual `Debug` so a stray `{:?}` on `Config` (or anything containing a
+// `SinkConfig`) can never leak a Lemmy account password to logs.
+impl std::fmt::Debug for SinkConfig {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SinkConfig::Discord { webhook_url } => f.debug_struct("Discord")
+				.field("webhook_url", webhook_url)
+				.finish(),
+			SinkConfig::Lemmy { instance_url, username, community, .. } => f.debug_struct("Lemmy")
+				.field("instance_url", instance_url)
+				.field("username", username)
+				.field("password", &"[redacted]")
+				.field("community", community)
+				.finish(),
+		}
+	}
+}
+
+impl SinkConfig {
+	pub fn build(&self) -> Box<dyn RecordingSink> {
+		match self {
+			SinkConfig::Discord { webhook_url } => Box::new(DiscordSink::new(webhook_url.clone())),
+			SinkConfig::Lemmy { instance_url, username, password, community } =>
+				Box::new(LemmySink::new(instance_url.clone(), username.clone(), password.clone(), community.clone())),
+		}
+	}
+}
@@ -0,0 +1,41 @@
+use anyhow::{bail, Context, Result};
+use image::GenericImageView;
+
+const SAMPLE_SIZE: u32 = 16;
+
+/// Downloads and downsamples `thumbnail_url`, averaging its pixels (weighted
+/// by saturation) into a representative `0xRRGGBB` color.
+pub async fn compute_color(client: &reqwest::Client, thumbnail_url: &str) -> Result<u32> {
+	let bytes = client.get(thumbnail_url).send().await?.bytes().await?;
+	let image = image::load_from_memory(&bytes).context("Failed to decode thumbnail image")?;
+	let small = image.resize(SAMPLE_SIZE, SAMPLE_SIZE, image::imageops::FilterType::Triangle);
+
+	let mut weighted_r = 0f64;
+	let mut weighted_g = 0f64;
+	let mut weighted_b = 0f64;
+	let mut total_weight = 0f64;
+
+	for (_, _, pixel) in small.pixels() {
+		let [r, g, b, _] = pixel.0;
+		let (r, g, b) = (r as f64, g as f64, b as f64);
+		let max = r.max(g).max(b);
+		let min = r.min(g).min(b);
+		let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+		// Never drop a pixel entirely, but favor saturated ones
+		let weight = 0.1 + saturation;
+
+		weighted_r += r * weight;
+		weighted_g += g * weight;
+		weighted_b += b * weight;
+		total_weight += weight;
+	}
+
+	if total_weight <= 0.0 {
+		bail!("Thumbnail had no pixels to sample");
+	}
+
+	let r = (weighted_r / total_weight).round() as u32;
+	let g = (weighted_g / total_weight).round() as u32;
+	let b = (weighted_b / total_weight).round() as u32;
+	Ok((r << 16) | (g << 8) | b)
+}